@@ -1,8 +1,11 @@
 use std::error::Error;
+use std::fmt;
 use csv;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
 use std::time::Instant;
 
+#[derive(Debug, Clone, Copy)]
 struct ColorRGB {
     r: u8,
     g: u8,
@@ -24,13 +27,167 @@ struct ColorYCbCr {
     cb: f64,
     cr: f64,
 }
+#[derive(Debug, Clone, Copy)]
+struct ColorLab {
+    l: f64,
+    a: f64,
+    b: f64,
+}
 struct NamedColor {
     name: String,
+    rgb: ColorRGB,
     ycbcr: ColorYCbCr,
+    lab: ColorLab,
+}
+
+/// Output format for `--batch` mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Csv,
+    Json,
+    Text,
+}
+
+/// Which color space `find_closest_color` measures distance in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorMetric {
+    /// Euclidean distance in YCbCr space (fast, but perceptually uneven).
+    YCbCr,
+    /// CIE76 Euclidean distance in CIELAB space (tracks human perception).
+    Lab,
+}
+
+/// A static k-d tree over the loaded named colors, built once so repeated
+/// queries avoid the O(n) linear scan `find_closest_color` used to do.
+mod kdtree {
+    use super::{ColorMetric, NamedColor};
+
+    fn point_for(color: &NamedColor, metric: ColorMetric) -> [f64; 3] {
+        match metric {
+            ColorMetric::YCbCr => [color.ycbcr.y, color.ycbcr.cb, color.ycbcr.cr],
+            ColorMetric::Lab => [color.lab.l, color.lab.a, color.lab.b],
+        }
+    }
+
+    fn squared_distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+        let dx = a[0] - b[0];
+        let dy = a[1] - b[1];
+        let dz = a[2] - b[2];
+        dx * dx + dy * dy + dz * dz
+    }
+
+    struct KdNode {
+        index: usize,
+        point: [f64; 3],
+        left: Option<Box<KdNode>>,
+        right: Option<Box<KdNode>>,
+    }
+
+    pub struct KdTree {
+        root: Option<Box<KdNode>>,
+    }
+
+    impl KdTree {
+        pub fn build(named_colors: &[NamedColor], metric: ColorMetric) -> Self {
+            let mut items: Vec<(usize, [f64; 3])> = named_colors
+                .iter()
+                .enumerate()
+                .map(|(index, color)| (index, point_for(color, metric)))
+                .collect();
+            KdTree { root: Self::build_recursive(&mut items, 0) }
+        }
+
+        fn build_recursive(items: &mut [(usize, [f64; 3])], depth: usize) -> Option<Box<KdNode>> {
+            if items.is_empty() {
+                return None;
+            }
+            let axis = depth % 3;
+            let mid = items.len() / 2;
+            items.select_nth_unstable_by(mid, |a, b| a.1[axis].partial_cmp(&b.1[axis]).unwrap());
+            let (index, point) = items[mid];
+
+            let (left_items, rest) = items.split_at_mut(mid);
+            let right_items = &mut rest[1..];
+            let left = Self::build_recursive(left_items, depth + 1);
+            let right = Self::build_recursive(right_items, depth + 1);
+            Some(Box::new(KdNode { index, point, left, right }))
+        }
+
+        /// Branch-and-bound nearest-neighbor search. Returns the index into
+        /// the original slice and the squared distance to the match.
+        pub fn nearest(&self, target: [f64; 3]) -> Option<(usize, f64)> {
+            let mut best: Option<(usize, f64)> = None;
+            if let Some(root) = &self.root {
+                Self::search(root, target, 0, &mut best);
+            }
+            best
+        }
+
+        fn search(node: &KdNode, target: [f64; 3], depth: usize, best: &mut Option<(usize, f64)>) {
+            let dist_sq = squared_distance(node.point, target);
+            if best.is_none_or(|(_, b)| dist_sq < b) {
+                *best = Some((node.index, dist_sq));
+            }
+
+            let axis = depth % 3;
+            let diff = target[axis] - node.point[axis];
+            let (near, far) = if diff < 0.0 { (&node.left, &node.right) } else { (&node.right, &node.left) };
+
+            if let Some(near_node) = near {
+                Self::search(near_node, target, depth + 1, best);
+            }
+            let best_dist_sq = best.map_or(f64::MAX, |(_, b)| b);
+            if diff * diff < best_dist_sq {
+                if let Some(far_node) = far {
+                    Self::search(far_node, target, depth + 1, best);
+                }
+            }
+        }
+
+        /// Branch-and-bound k-nearest-neighbor search. Returns up to `n`
+        /// `(index, squared distance)` pairs, nearest-first.
+        pub fn nearest_n(&self, target: [f64; 3], n: usize) -> Vec<(usize, f64)> {
+            let mut best: Vec<(usize, f64)> = Vec::with_capacity(n);
+            if n > 0 {
+                if let Some(root) = &self.root {
+                    Self::search_n(root, target, 0, n, &mut best);
+                }
+            }
+            best
+        }
+
+        fn search_n(node: &KdNode, target: [f64; 3], depth: usize, n: usize, best: &mut Vec<(usize, f64)>) {
+            let dist_sq = squared_distance(node.point, target);
+            if best.len() < n {
+                let pos = best.partition_point(|&(_, d)| d < dist_sq);
+                best.insert(pos, (node.index, dist_sq));
+            } else if dist_sq < best.last().map_or(f64::MAX, |&(_, d)| d) {
+                let pos = best.partition_point(|&(_, d)| d < dist_sq);
+                best.insert(pos, (node.index, dist_sq));
+                best.pop();
+            }
+
+            let axis = depth % 3;
+            let diff = target[axis] - node.point[axis];
+            let (near, far) = if diff < 0.0 { (&node.left, &node.right) } else { (&node.right, &node.left) };
+
+            if let Some(near_node) = near {
+                Self::search_n(near_node, target, depth + 1, n, best);
+            }
+            let worst_dist_sq = if best.len() < n { f64::MAX } else { best.last().map_or(f64::MAX, |&(_, d)| d) };
+            if diff * diff < worst_dist_sq {
+                if let Some(far_node) = far {
+                    Self::search_n(far_node, target, depth + 1, n, best);
+                }
+            }
+        }
+    }
 }
 
 mod user_input {
     use std::io;
+    use std::io::BufRead;
+
     pub fn get_input(prompt: &str) -> String{
         println!("{}",prompt);
         let mut input = String::new();
@@ -40,6 +197,17 @@ mod user_input {
         }
         input.trim().to_string()
     }
+
+    /// Reads one line from stdin for batch mode, returning `None` on EOF
+    /// (the `Ok(0)` that `read_line` returns once the stream is exhausted).
+    pub fn read_batch_line() -> Option<String> {
+        let mut input = String::new();
+        match io::stdin().lock().read_line(&mut input) {
+            Ok(0) => None,
+            Ok(_) => Some(input.trim().to_string()),
+            Err(_) => None,
+        }
+    }
 }
 
 fn convert_ycbcr(rgb: ColorRGB) -> ColorYCbCr {
@@ -52,6 +220,47 @@ fn convert_ycbcr(rgb: ColorRGB) -> ColorYCbCr {
     ColorYCbCr { y, cb, cr }
 }
 
+// D65 reference white point used to normalize the XYZ components before
+// applying the CIE Lab nonlinearity.
+const D65_XN: f64 = 0.95047;
+const D65_YN: f64 = 1.0;
+const D65_ZN: f64 = 1.08883;
+
+fn convert_lab(rgb: &ColorRGB) -> ColorLab {
+    let linearize = |c: f64| -> f64 {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let r = linearize(rgb.r as f64 / 255.0);
+    let g = linearize(rgb.g as f64 / 255.0);
+    let b = linearize(rgb.b as f64 / 255.0);
+
+    let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+
+    let f = |t: f64| -> f64 {
+        const DELTA: f64 = 6.0 / 29.0;
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+    let fx = f(x / D65_XN);
+    let fy = f(y / D65_YN);
+    let fz = f(z / D65_ZN);
+
+    ColorLab {
+        l: 116.0 * fy - 16.0,
+        a: 500.0 * (fx - fy),
+        b: 200.0 * (fy - fz),
+    }
+}
+
 fn convert_hex(ycbcr: ColorYCbCr) -> String {
     let r_norm = ycbcr.y + 1.402 * ycbcr.cr;
     let g_norm = ycbcr.y - 0.344136 * ycbcr.cb - 0.714136 * ycbcr.cr;
@@ -62,48 +271,290 @@ fn convert_hex(ycbcr: ColorYCbCr) -> String {
     format!("#{:02X}{:02X}{:02X}", r, g, b)
 }
 
-fn hex_to_rgb(hex: &str) -> Result<ColorRGB, &'static str> {
-    // Ensure the input is exactly 7 characters long and starts with '#'
-    if hex.len() != 7 || !hex.starts_with('#') {
-        return Err("Invalid hex format. Must be '#RRGGBB'.");
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ColorParseError {
+    /// A `#...` hex form wasn't 3, 4, 6, or 8 digits long.
+    InvalidLength(usize),
+    /// A hex form contained a non hex digit character.
+    InvalidDigit(char),
+    /// The input wasn't `#...` hex and didn't match a recognized
+    /// `rgb()`/`rgba()`/`hsl()` function, or its arguments were malformed.
+    UnknownFormat(String),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorParseError::InvalidLength(len) => {
+                write!(f, "invalid hex length: expected 3, 4, 6, or 8 digits, got {}", len)
+            }
+            ColorParseError::InvalidDigit(c) => write!(f, "invalid hex digit: '{}'", c),
+            ColorParseError::UnknownFormat(s) => write!(f, "unrecognized color format: '{}'", s),
+        }
+    }
+}
+
+impl Error for ColorParseError {}
+
+/// Parses a color from `#RGB`/`#RGBA`/`#RRGGBB`/`#RRGGBBAA` hex, or a CSS
+/// `rgb(...)`, `rgba(...)`, or `hsl(...)` function. Alpha components, where
+/// present, are accepted but discarded since `ColorRGB` carries no alpha.
+fn parse_color(input: &str) -> Result<ColorRGB, ColorParseError> {
+    let trimmed = input.trim();
+
+    if let Some(hex) = trimmed.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    let lower = trimmed.to_lowercase();
+    if let Some(inner) = lower.strip_prefix("rgba(").and_then(|s| s.strip_suffix(')')) {
+        return parse_rgb_function(inner, 4);
+    }
+    if let Some(inner) = lower.strip_prefix("rgb(").and_then(|s| s.strip_suffix(')')) {
+        return parse_rgb_function(inner, 3);
+    }
+    if let Some(inner) = lower.strip_prefix("hsl(").and_then(|s| s.strip_suffix(')')) {
+        return parse_hsl_function(inner);
+    }
+
+    Err(ColorParseError::UnknownFormat(trimmed.to_string()))
+}
+
+fn parse_hex(hex: &str) -> Result<ColorRGB, ColorParseError> {
+    // Work in `char`s, not bytes: a non-ASCII character makes byte offsets
+    // land mid-codepoint, and `hex.len()` (a byte count) would otherwise
+    // misjudge the digit count for multi-byte input.
+    let digits: Vec<char> = hex.chars().collect();
+    match digits.len() {
+        3 | 4 => {
+            let expanded: String = digits.iter().flat_map(|&c| [c, c]).collect();
+            parse_hex(&expanded)
+        }
+        6 | 8 => {
+            let r = hex_byte(&digits, 0)?;
+            let g = hex_byte(&digits, 2)?;
+            let b = hex_byte(&digits, 4)?;
+            Ok(ColorRGB { r, g, b })
+        }
+        other => Err(ColorParseError::InvalidLength(other)),
     }
+}
+
+fn hex_byte(digits: &[char], start: usize) -> Result<u8, ColorParseError> {
+    let hi = digits[start].to_digit(16).ok_or(ColorParseError::InvalidDigit(digits[start]))?;
+    let lo = digits[start + 1].to_digit(16).ok_or(ColorParseError::InvalidDigit(digits[start + 1]))?;
+    Ok((hi * 16 + lo) as u8)
+}
 
-    let r_hex = &hex[1..3];
-    let g_hex = &hex[3..5];
-    let b_hex = &hex[5..7];
+/// Parses the inner `r, g, b[, a]` of an `rgb()`/`rgba()` function.
+/// `expected_components` is 3 for `rgb()` and 4 for `rgba()`.
+fn parse_rgb_function(inner: &str, expected_components: usize) -> Result<ColorRGB, ColorParseError> {
+    let parts: Vec<&str> = inner.split(',').map(|s| s.trim()).collect();
+    if parts.len() != expected_components {
+        return Err(ColorParseError::UnknownFormat(inner.to_string()));
+    }
+    let component = |s: &str| s.parse::<u8>().map_err(|_| ColorParseError::UnknownFormat(inner.to_string()));
+    Ok(ColorRGB { r: component(parts[0])?, g: component(parts[1])?, b: component(parts[2])? })
+}
 
-    let r = u8::from_str_radix(r_hex, 16).map_err(|_| "Invalid hex R component")?;
-    let g = u8::from_str_radix(g_hex, 16).map_err(|_| "Invalid hex G component")?;
-    let b = u8::from_str_radix(b_hex, 16).map_err(|_| "Invalid hex B component")?;
+/// Parses the inner `h, s%, l%` of an `hsl()` function and converts to RGB.
+fn parse_hsl_function(inner: &str) -> Result<ColorRGB, ColorParseError> {
+    let parts: Vec<&str> = inner.split(',').map(|s| s.trim()).collect();
+    if parts.len() != 3 {
+        return Err(ColorParseError::UnknownFormat(inner.to_string()));
+    }
+    let malformed = || ColorParseError::UnknownFormat(inner.to_string());
+    let h: f64 = parts[0].parse().map_err(|_| malformed())?;
+    let s: f64 = parts[1].trim_end_matches('%').parse().map_err(|_| malformed())?;
+    let l: f64 = parts[2].trim_end_matches('%').parse().map_err(|_| malformed())?;
+    Ok(hsl_to_rgb(h, s / 100.0, l / 100.0))
+}
 
-    Ok(ColorRGB { r, g, b })
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> ColorRGB {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h_prime = (h.rem_euclid(360.0)) / 60.0;
+    let x = c * (1.0 - ((h_prime % 2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r1, g1, b1) = match h_prime as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    ColorRGB {
+        r: ((r1 + m) * 255.0).round() as u8,
+        g: ((g1 + m) * 255.0).round() as u8,
+        b: ((b1 + m) * 255.0).round() as u8,
+    }
 }
-fn find_closest_color(target_ycbcr: ColorYCbCr, named_colors: &[NamedColor]) -> (&str, f64, String) {
+fn find_closest_color<'a>(
+    target_ycbcr: ColorYCbCr,
+    target_lab: ColorLab,
+    named_colors: &'a [NamedColor],
+    metric: ColorMetric,
+    tree: &kdtree::KdTree,
+) -> (&'a str, f64, String) {
     if named_colors.is_empty() {
         return ("", f64::NAN, String::from("NULL"));
     }
-    let mut closest_name = "";
-    let mut min_distance_sq = f64::MAX;
-    let mut closest_color_YCbCr = ColorYCbCr { y: 0.0, cb: 0.0, cr: 0.0 };
-    for color in named_colors {
-        let distance_sq = color_distance_sq(target_ycbcr, color.ycbcr);
+    let target_point = match metric {
+        ColorMetric::YCbCr => [target_ycbcr.y, target_ycbcr.cb, target_ycbcr.cr],
+        ColorMetric::Lab => [target_lab.l, target_lab.a, target_lab.b],
+    };
+    let (index, min_distance_sq) = tree
+        .nearest(target_point)
+        .expect("tree is non-empty whenever named_colors is non-empty");
+    let closest = &named_colors[index];
+    let min_distance = min_distance_sq.sqrt();
+    let closest_color_hex = convert_hex(closest.ycbcr);
+    (&closest.name, min_distance, closest_color_hex)
+}
+
+/// One entry in a ranked nearest-colors report.
+struct ColorMatch<'a> {
+    name: &'a str,
+    hex: String,
+    rgb: ColorRGB,
+    distance: f64,
+}
+
+/// Returns the `n` nearest named colors to `target`, ordered nearest-first,
+/// via the same k-d tree `find_closest_color` uses for a single match.
+fn find_nearest_n<'a>(
+    target_ycbcr: ColorYCbCr,
+    target_lab: ColorLab,
+    named_colors: &'a [NamedColor],
+    metric: ColorMetric,
+    tree: &kdtree::KdTree,
+    n: usize,
+) -> Vec<ColorMatch<'a>> {
+    let target_point = match metric {
+        ColorMetric::YCbCr => [target_ycbcr.y, target_ycbcr.cb, target_ycbcr.cr],
+        ColorMetric::Lab => [target_lab.l, target_lab.a, target_lab.b],
+    };
 
-        if distance_sq < min_distance_sq {
-            min_distance_sq = distance_sq;
-            closest_name = &color.name;
-            closest_color_YCbCr = color.ycbcr;
+    tree.nearest_n(target_point, n)
+        .into_iter()
+        .map(|(index, distance_sq)| {
+            let color = &named_colors[index];
+            ColorMatch {
+                name: &color.name,
+                hex: convert_hex(color.ycbcr),
+                rgb: color.rgb,
+                distance: distance_sq.sqrt(),
+            }
+        })
+        .collect()
+}
+
+/// WCAG relative luminance of an sRGB color (0.0 = black, 1.0 = white).
+fn relative_luminance(rgb: &ColorRGB) -> f64 {
+    let channel = |c: u8| -> f64 {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
         }
+    };
+    0.2126 * channel(rgb.r) + 0.7152 * channel(rgb.g) + 0.0722 * channel(rgb.b)
+}
+
+/// WCAG contrast ratio between two relative luminances, in `[1.0, 21.0]`.
+fn contrast_ratio(l1: f64, l2: f64) -> f64 {
+    let (lighter, darker) = if l1 >= l2 { (l1, l2) } else { (l2, l1) };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+/// Black or white, whichever contrasts more strongly against `rgb` per WCAG.
+fn readable_foreground(rgb: &ColorRGB) -> ColorRGB {
+    let luminance = relative_luminance(rgb);
+    let black = ColorRGB { r: 0, g: 0, b: 0 };
+    let white = ColorRGB { r: 255, g: 255, b: 255 };
+    if contrast_ratio(luminance, relative_luminance(&black)) >= contrast_ratio(luminance, relative_luminance(&white)) {
+        black
+    } else {
+        white
     }
-    let min_distance = min_distance_sq.sqrt();
-    let closest_color_hex = convert_hex(closest_color_YCbCr);
-    (closest_name, min_distance, closest_color_hex)
 }
 
-fn color_distance_sq(c1: ColorYCbCr, c2: ColorYCbCr) -> f64 {
-    let dy = c1.y - c2.y;
-    let dcb = c1.cb - c2.cb;
-    let dcr = c1.cr - c2.cr;
-    dy * dy + dcb * dcb + dcr * dcr
+/// Renders a 24-bit ANSI truecolor swatch with `label` overlaid in a
+/// readable foreground color, e.g. for printing alongside a hex string.
+fn render_swatch(rgb: &ColorRGB, label: &str) -> String {
+    let fg = readable_foreground(rgb);
+    format!(
+        "\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m {} \x1b[0m",
+        rgb.r, rgb.g, rgb.b, fg.r, fg.g, fg.b, label
+    )
+}
+
+/// One row of `--batch` mode output.
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    input: String,
+    matched_name: String,
+    matched_hex: String,
+    distance: f64,
+}
+
+/// Reads one color per line from stdin until EOF, matches each against
+/// `named_colors`, and streams a `BatchResult` row to stdout in the
+/// requested format for each line that parses. Empty lines are skipped;
+/// lines that fail to parse are reported on stderr and produce no row.
+fn run_batch_mode(
+    named_colors: &[NamedColor],
+    metric: ColorMetric,
+    tree: &kdtree::KdTree,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut csv_writer = if format == OutputFormat::Csv {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer.write_record(["input", "matched_name", "matched_hex", "distance"])?;
+        Some(writer)
+    } else {
+        None
+    };
+
+    while let Some(line) = user_input::read_batch_line() {
+        if line.is_empty() {
+            continue;
+        }
+        match parse_color(&line) {
+            Ok(rgb) => {
+                let target_lab = convert_lab(&rgb);
+                let target_ycbcr = convert_ycbcr(rgb);
+                let (name, distance, hex) =
+                    find_closest_color(target_ycbcr, target_lab, named_colors, metric, tree);
+                let result = BatchResult {
+                    input: line,
+                    matched_name: name.to_string(),
+                    matched_hex: hex,
+                    distance,
+                };
+                match format {
+                    OutputFormat::Csv => {
+                        let writer = csv_writer.as_mut().expect("csv writer set for Csv format");
+                        writer.write_record([
+                            &result.input,
+                            &result.matched_name,
+                            &result.matched_hex,
+                            &format!("{:.4}", result.distance),
+                        ])?;
+                        writer.flush()?;
+                    }
+                    OutputFormat::Json => println!("{}", serde_json::to_string(&result)?),
+                    OutputFormat::Text => println!(
+                        "{} -> {} ({}) distance {:.4}",
+                        result.input, result.matched_name, result.matched_hex, result.distance
+                    ),
+                }
+            }
+            Err(e) => eprintln!("Skipping input '{}': {}", line, e),
+        }
+    }
+    Ok(())
 }
 
 fn load_and_process_colors(file_path: &str) -> Result<Vec<NamedColor>, Box<dyn Error>> {
@@ -115,12 +566,15 @@ fn load_and_process_colors(file_path: &str) -> Result<Vec<NamedColor>, Box<dyn E
     for result in rdr.deserialize() {
         let record: CsvColorRecord = result?;
 
-        match hex_to_rgb(&record.hex) {
+        match parse_color(&record.hex) {
             Ok(rgb) => {
+                let lab = convert_lab(&rgb);
                 let ycbcr = convert_ycbcr(rgb);
                 named_colors.push(NamedColor {
                     name: record.name,
+                    rgb,
                     ycbcr,
+                    lab,
                 });
             },
             Err(e) => {
@@ -132,6 +586,47 @@ fn load_and_process_colors(file_path: &str) -> Result<Vec<NamedColor>, Box<dyn E
     Ok(named_colors)
 }
 
+/// Reads `--top N` from the CLI args, defaulting to 1 (just the closest match).
+fn top_n_from_args() -> usize {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|a| a == "--top")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(1)
+}
+
+fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|a| a == flag)
+}
+
+/// Reads `--metric ycbcr|lab` from the CLI args, if present.
+fn metric_from_args() -> Option<ColorMetric> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|a| a == "--metric")
+        .and_then(|i| args.get(i + 1))
+        .map(|v| match v.to_lowercase().as_str() {
+            "lab" => ColorMetric::Lab,
+            _ => ColorMetric::YCbCr,
+        })
+}
+
+/// Reads `--format csv|json|text` from the CLI args, defaulting to `text`.
+fn output_format_from_args() -> OutputFormat {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|a| a == "--format")
+        .and_then(|i| args.get(i + 1))
+        .map(|v| match v.to_lowercase().as_str() {
+            "csv" => OutputFormat::Csv,
+            "json" => OutputFormat::Json,
+            _ => OutputFormat::Text,
+        })
+        .unwrap_or(OutputFormat::Text)
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     const FILE_PATH: &str = "input/color_names.csv";
     println!("Loading colors from: {}", FILE_PATH);
@@ -152,20 +647,366 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
 
-    let input = user_input::get_input("Enter a Hex Color (e.g., #123456): ");
+    let batch_mode = has_flag("--batch");
+    let metric = match metric_from_args() {
+        Some(metric) => metric,
+        None if batch_mode => ColorMetric::YCbCr,
+        None => {
+            let metric_input = user_input::get_input("Choose distance metric (ycbcr/lab) [ycbcr]: ");
+            match metric_input.to_lowercase().as_str() {
+                "lab" => ColorMetric::Lab,
+                _ => ColorMetric::YCbCr,
+            }
+        }
+    };
+
+    let tree = kdtree::KdTree::build(&named_colors, metric);
+
+    if batch_mode {
+        return run_batch_mode(&named_colors, metric, &tree, output_format_from_args());
+    }
+
+    let top_n = top_n_from_args();
+    let show_swatches = std::io::stdout().is_terminal();
+
+    let input = user_input::get_input(
+        "Enter a color (#RGB, #RGBA, #RRGGBB, #RRGGBBAA, rgb(), rgba(), or hsl()): ",
+    );
     let start_time = Instant::now();
-    match hex_to_rgb(&input) {
+    match parse_color(&input) {
         Ok(input_rgb) => {
+            let target_lab = convert_lab(&input_rgb);
             let target_ycbcr = convert_ycbcr(input_rgb);
-            let (closest_name, distance, closest_hex) = find_closest_color(target_ycbcr, &named_colors);
-            let end_time = Instant::now();
-            println!("Processing time: {:.2?}", end_time - start_time);
-            println!("Closest Named Color: {} - {}", closest_name, closest_hex);
-            println!("Color Difference (Euclidean Distance in YCbCr space): {:.4}", distance);
+            if show_swatches {
+                println!("Input: {}", render_swatch(&input_rgb, &convert_hex(target_ycbcr)));
+            }
+
+            if top_n <= 1 {
+                let (closest_name, distance, closest_hex) =
+                    find_closest_color(target_ycbcr, target_lab, &named_colors, metric, &tree);
+                let end_time = Instant::now();
+                println!("Processing time: {:.2?}", end_time - start_time);
+                if show_swatches {
+                    let closest_rgb = parse_color(&closest_hex).unwrap_or(input_rgb);
+                    println!(
+                        "Closest Named Color: {} - {} {}",
+                        closest_name,
+                        closest_hex,
+                        render_swatch(&closest_rgb, &closest_hex)
+                    );
+                } else {
+                    println!("Closest Named Color: {} - {}", closest_name, closest_hex);
+                }
+                match metric {
+                    ColorMetric::YCbCr => {
+                        println!("Color Difference (Euclidean Distance in YCbCr space): {:.4}", distance)
+                    }
+                    ColorMetric::Lab => {
+                        println!("Color Difference (Delta E, CIE76 in CIELAB space): {:.4}", distance)
+                    }
+                }
+            } else {
+                let matches = find_nearest_n(target_ycbcr, target_lab, &named_colors, metric, &tree, top_n);
+                let end_time = Instant::now();
+                println!("Processing time: {:.2?}", end_time - start_time);
+                let input_luminance = relative_luminance(&input_rgb);
+                println!("Top {} nearest named colors:", matches.len());
+                for (rank, m) in matches.iter().enumerate() {
+                    let contrast = contrast_ratio(input_luminance, relative_luminance(&m.rgb));
+                    if show_swatches {
+                        println!(
+                            "{}. {} - {} {} (distance {:.4}, contrast ratio {:.2}:1)",
+                            rank + 1,
+                            m.name,
+                            m.hex,
+                            render_swatch(&m.rgb, &m.hex),
+                            m.distance,
+                            contrast
+                        );
+                    } else {
+                        println!(
+                            "{}. {} - {} (distance {:.4}, contrast ratio {:.2}:1)",
+                            rank + 1,
+                            m.name,
+                            m.hex,
+                            m.distance,
+                            contrast
+                        );
+                    }
+                }
+            }
         }
         Err(e) => {
             println!("\nError processing input: {}", e);
         }
     }
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {} to be within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn lab_of_black_is_zero() {
+        let lab = convert_lab(&ColorRGB { r: 0, g: 0, b: 0 });
+        assert_close(lab.l, 0.0, 1e-6);
+        assert_close(lab.a, 0.0, 1e-6);
+        assert_close(lab.b, 0.0, 1e-6);
+    }
+
+    #[test]
+    fn lab_of_white_is_l_100_neutral() {
+        let lab = convert_lab(&ColorRGB { r: 255, g: 255, b: 255 });
+        assert_close(lab.l, 100.0, 1e-3);
+        // The rounded D65/XYZ matrix coefficients used here don't put pure
+        // white exactly on the neutral axis; they leave a small residual
+        // (~0.005-0.01) in a/b rather than 0.0.
+        assert_close(lab.a, 0.0, 2e-2);
+        assert_close(lab.b, 0.0, 2e-2);
+    }
+
+    fn sample_named_colors() -> Vec<NamedColor> {
+        let hexes = [
+            "#000000", "#FFFFFF", "#FF0000", "#00FF00", "#0000FF", "#112233", "#A1B2C3", "#5E2750",
+            "#C0FFEE", "#123ABC", "#7F7F7F", "#010203", "#FEDCBA", "#336699", "#998877", "#001122",
+        ];
+        hexes
+            .iter()
+            .enumerate()
+            .map(|(i, hex)| {
+                let rgb = parse_color(hex).unwrap();
+                NamedColor {
+                    name: format!("color-{}", i),
+                    rgb,
+                    ycbcr: convert_ycbcr(rgb),
+                    lab: convert_lab(&rgb),
+                }
+            })
+            .collect()
+    }
+
+    fn linear_nearest(named_colors: &[NamedColor], metric: ColorMetric, target: [f64; 3]) -> (usize, f64) {
+        named_colors
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let point = match metric {
+                    ColorMetric::YCbCr => [c.ycbcr.y, c.ycbcr.cb, c.ycbcr.cr],
+                    ColorMetric::Lab => [c.lab.l, c.lab.a, c.lab.b],
+                };
+                let dx = point[0] - target[0];
+                let dy = point[1] - target[1];
+                let dz = point[2] - target[2];
+                (i, dx * dx + dy * dy + dz * dz)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn kdtree_nearest_matches_linear_scan() {
+        let named_colors = sample_named_colors();
+        for metric in [ColorMetric::YCbCr, ColorMetric::Lab] {
+            let tree = kdtree::KdTree::build(&named_colors, metric);
+            let targets: [[f64; 3]; 4] =
+                [[0.0, 0.0, 0.0], [0.5, 0.1, -0.2], [100.0, 20.0, -30.0], [0.3, -0.15, 0.15]];
+            for target in targets {
+                let (expected_index, expected_dist_sq) = linear_nearest(&named_colors, metric, target);
+                let (tree_index, tree_dist_sq) = tree.nearest(target).unwrap();
+                assert_close(tree_dist_sq, expected_dist_sq, 1e-9);
+                assert_eq!(
+                    named_colors[tree_index].name, named_colors[expected_index].name,
+                    "kd-tree and linear scan disagreed for metric {:?} at {:?}",
+                    metric, target
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn kdtree_nearest_n_matches_linear_scan() {
+        let named_colors = sample_named_colors();
+        let metric = ColorMetric::Lab;
+        let tree = kdtree::KdTree::build(&named_colors, metric);
+        let target = [50.0, 10.0, -5.0];
+
+        let mut linear: Vec<(usize, f64)> = named_colors
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let point = [c.lab.l, c.lab.a, c.lab.b];
+                let dx = point[0] - target[0];
+                let dy = point[1] - target[1];
+                let dz = point[2] - target[2];
+                (i, dx * dx + dy * dy + dz * dz)
+            })
+            .collect();
+        linear.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        let expected: Vec<f64> = linear.iter().take(5).map(|&(_, d)| d).collect();
+
+        let actual: Vec<f64> = tree.nearest_n(target, 5).into_iter().map(|(_, d)| d).collect();
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert_close(*a, *e, 1e-9);
+        }
+    }
+
+    fn assert_rgb(rgb: ColorRGB, r: u8, g: u8, b: u8) {
+        assert_eq!((rgb.r, rgb.g, rgb.b), (r, g, b));
+    }
+
+    #[test]
+    fn parse_color_hex_forms_agree() {
+        assert_rgb(parse_color("#112233").unwrap(), 0x11, 0x22, 0x33);
+        assert_rgb(parse_color("#11223344").unwrap(), 0x11, 0x22, 0x33);
+        assert_rgb(parse_color("#1a2").unwrap(), 0x11, 0xaa, 0x22);
+        assert_rgb(parse_color("#1a2f").unwrap(), 0x11, 0xaa, 0x22);
+    }
+
+    #[test]
+    fn parse_color_css_functions() {
+        assert_rgb(parse_color("rgb(255, 0, 128)").unwrap(), 255, 0, 128);
+        assert_rgb(parse_color("rgba(255, 0, 128, 0.5)").unwrap(), 255, 0, 128);
+        assert_rgb(parse_color("hsl(210, 50%, 40%)").unwrap(), 0x33, 0x66, 0x99);
+    }
+
+    #[test]
+    fn parse_color_invalid_length_is_reported() {
+        assert_eq!(parse_color("#12345").unwrap_err(), ColorParseError::InvalidLength(5));
+    }
+
+    #[test]
+    fn parse_color_unknown_format_is_reported() {
+        assert_eq!(
+            parse_color("not-a-color").unwrap_err(),
+            ColorParseError::UnknownFormat("not-a-color".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_color_rejects_multibyte_hex_digits_without_panicking() {
+        // Regression test: byte-slicing a multi-byte char used to panic here
+        // instead of returning a structured parse error. Use an 8-digit hex
+        // string so the multi-byte char lands in `hex_byte`'s digit decode
+        // instead of tripping the length check first.
+        match parse_color("#1é234567") {
+            Err(ColorParseError::InvalidDigit(_)) => {}
+            other => panic!("expected InvalidDigit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn hsl_to_rgb_primary_hues() {
+        assert_rgb(hsl_to_rgb(0.0, 1.0, 0.5), 255, 0, 0);
+        assert_rgb(hsl_to_rgb(120.0, 1.0, 0.5), 0, 255, 0);
+        assert_rgb(hsl_to_rgb(240.0, 1.0, 0.5), 0, 0, 255);
+    }
+
+    #[test]
+    fn hsl_to_rgb_grayscale_has_no_saturation() {
+        assert_rgb(hsl_to_rgb(0.0, 0.0, 0.0), 0, 0, 0);
+        assert_rgb(hsl_to_rgb(0.0, 0.0, 1.0), 255, 255, 255);
+    }
+
+    #[test]
+    fn contrast_ratio_of_black_and_white_is_21() {
+        let black = relative_luminance(&ColorRGB { r: 0, g: 0, b: 0 });
+        let white = relative_luminance(&ColorRGB { r: 255, g: 255, b: 255 });
+        assert_close(contrast_ratio(black, white), 21.0, 1e-9);
+        // Order of the two luminances shouldn't matter.
+        assert_close(contrast_ratio(white, black), 21.0, 1e-9);
+    }
+
+    #[test]
+    fn contrast_ratio_of_a_color_with_itself_is_1() {
+        let l = relative_luminance(&ColorRGB { r: 123, g: 45, b: 200 });
+        assert_close(contrast_ratio(l, l), 1.0, 1e-9);
+    }
+
+    #[test]
+    fn find_nearest_n_matches_linear_scan_and_orders_nearest_first() {
+        let named_colors = sample_named_colors();
+        let metric = ColorMetric::Lab;
+        let tree = kdtree::KdTree::build(&named_colors, metric);
+        let target_rgb = ColorRGB { r: 10, g: 20, b: 30 };
+        let target_ycbcr = convert_ycbcr(target_rgb);
+        let target_lab = convert_lab(&target_rgb);
+
+        let matches = find_nearest_n(target_ycbcr, target_lab, &named_colors, metric, &tree, 5);
+        assert_eq!(matches.len(), 5);
+
+        let (expected_index, _) = linear_nearest(&named_colors, metric, [target_lab.l, target_lab.a, target_lab.b]);
+        assert_eq!(matches[0].name, named_colors[expected_index].name);
+
+        for pair in matches.windows(2) {
+            assert!(pair[0].distance <= pair[1].distance, "matches were not ordered nearest-first");
+        }
+    }
+
+    #[test]
+    fn batch_result_round_trips_through_json() {
+        let result = BatchResult {
+            input: "#ff00ff".to_string(),
+            matched_name: "magenta".to_string(),
+            matched_hex: "#ff00ff".to_string(),
+            distance: 1.25,
+        };
+        let json = serde_json::to_string(&result).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["input"], "#ff00ff");
+        assert_eq!(value["matched_name"], "magenta");
+        assert_eq!(value["matched_hex"], "#ff00ff");
+        assert_eq!(value["distance"], 1.25);
+    }
+
+    #[test]
+    fn batch_result_serializes_to_csv() {
+        let result = BatchResult {
+            input: "red".to_string(),
+            matched_name: "crimson".to_string(),
+            matched_hex: "#dc143c".to_string(),
+            distance: 3.5,
+        };
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record(["input", "matched_name", "matched_hex", "distance"]).unwrap();
+        writer
+            .write_record([
+                &result.input,
+                &result.matched_name,
+                &result.matched_hex,
+                &format!("{:.4}", result.distance),
+            ])
+            .unwrap();
+        let csv_bytes = writer.into_inner().unwrap();
+        let csv_text = String::from_utf8(csv_bytes).unwrap();
+        let mut lines = csv_text.lines();
+        assert_eq!(lines.next().unwrap(), "input,matched_name,matched_hex,distance");
+        assert_eq!(lines.next().unwrap(), "red,crimson,#dc143c,3.5000");
+    }
+
+    #[test]
+    fn readable_foreground_picks_white_on_black_and_black_on_white() {
+        assert_rgb(readable_foreground(&ColorRGB { r: 0, g: 0, b: 0 }), 255, 255, 255);
+        assert_rgb(readable_foreground(&ColorRGB { r: 255, g: 255, b: 255 }), 0, 0, 0);
+    }
+
+    #[test]
+    fn render_swatch_embeds_label_and_foreground_escape() {
+        let swatch = render_swatch(&ColorRGB { r: 0, g: 0, b: 0 }, "#000000");
+        assert!(swatch.contains("#000000"), "swatch should contain the label");
+        assert!(swatch.contains("\x1b[48;2;0;0;0m"), "swatch should set the background color");
+        assert!(swatch.contains("\x1b[38;2;255;255;255m"), "swatch should pick white as the readable foreground");
+        assert!(swatch.ends_with("\x1b[0m"), "swatch should reset styling at the end");
+    }
 }
\ No newline at end of file